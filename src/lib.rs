@@ -1,63 +1,114 @@
 use anyhow::Result;
-use hdf5::types::VarLenUnicode;
+use hdf5::types::{FloatSize, IntSize, TypeDescriptor, VarLenUnicode};
 use hdf5::File;
 use std::io::{self, Write};
 
+mod export;
+mod matrix;
+mod stats;
+pub use export::{export, ExportFormat};
+pub use matrix::{show_matrix_head, SparseRender};
+pub use stats::show_stats;
+
+/// Rows are pulled in chunks of this size so a single `RowIter` never holds
+/// more than one chunk's worth of decoded data in memory. `export` and
+/// `stats` stream obs/var rows the same way, so they share this constant
+/// rather than risk their chunking drifting out of sync with it.
+pub(crate) const CHUNK_SIZE: usize = 1000;
+
+/// Number of digits printed after the decimal point for float columns.
+pub(crate) const FLOAT_PRECISION: usize = 6;
+
+/// Placeholder printed for values masked out by a `nullable-*` column's mask
+/// or a categorical's `-1` missing-category code.
+pub(crate) const NA_TOKEN: &str = "NA";
+
 // A general struct to read data from an h5ad file
 struct DataReader {
     headers: Vec<String>,
     group: hdf5::Group,
     total_rows: usize,
     encoding_types: Vec<String>,
-    categorical_data: Vec<Option<(Vec<String>, Vec<u32>)>>,
+    // Categorical columns keep only their (small) category vocabulary and
+    // `ordered` flag resident in memory, plus a handle to the `codes`
+    // dataset so the actual code values can be hyperslab-read on demand.
+    categorical_data: Vec<Option<(Vec<String>, hdf5::Dataset, bool)>>,
+    // Handle to the backing group for any other group-encoded column
+    // (currently `nullable-integer`/`nullable-boolean`), so their `values`
+    // and `mask` datasets can be hyperslab-read on demand.
+    sub_groups: Vec<Option<hdf5::Group>>,
+    // In raw mode, categorical columns yield their integer `codes` instead
+    // of the decoded category strings.
+    raw: bool,
 }
 
 // Implement the DataReader struct
 impl DataReader {
-    fn new(file: &File, group_name: &str) -> Result<Self> {
+    /// `columns`, when given, restricts `headers`/`encoding_types` to that
+    /// subset of fields, in the order requested, instead of every field in
+    /// the group.
+    fn new(file: &File, group_name: &str, columns: Option<&[String]>, raw: bool) -> Result<Self> {
         let group = file.group(group_name)?;
-        let headers = group.member_names()?;
+        let headers = match columns {
+            Some(selected) => selected.to_vec(),
+            None => group.member_names()?,
+        };
 
         let mut encoding_types = Vec::new();
         let mut categorical_data = Vec::new();
+        let mut sub_groups = Vec::new();
         let mut total_rows = 0;
 
         for (i, name) in headers.iter().enumerate() {
-            let encoding_type = match group.dataset(name) {
+            let (encoding_type, sub_group) = match group.dataset(name) {
                 Ok(dataset) => {
                     if i == 0 {
                         total_rows = dataset.shape()[0];
                     }
-                    dataset
+                    let encoding_type = dataset
                         .attr("encoding-type")?
                         .read_scalar::<VarLenUnicode>()?
+                        .to_string();
+                    (encoding_type, None)
                 }
                 Err(_) => {
                     let sub_group = group.group(name)?;
-                    if i == 0 {
-                        total_rows = sub_group.dataset("codes")?.shape()[0];
-                    }
-                    sub_group
+                    let encoding_type = sub_group
                         .attr("encoding-type")?
                         .read_scalar::<VarLenUnicode>()?
+                        .to_string();
+                    // `categorical` groups are keyed by `codes`; `nullable-*`
+                    // groups by `values` - both share the column's row count.
+                    let shape_dataset = if encoding_type == "categorical" {
+                        "codes"
+                    } else {
+                        "values"
+                    };
+                    if i == 0 {
+                        total_rows = sub_group.dataset(shape_dataset)?.shape()[0];
+                    }
+                    (encoding_type, Some(sub_group))
                 }
             };
 
-            encoding_types.push(encoding_type.to_string());
-
             if encoding_type == "categorical" {
-                let sub_group = group.group(name)?;
+                let sub_group = sub_group.expect("categorical column must be backed by a group");
                 let categories: Vec<String> = sub_group
                     .dataset("categories")?
                     .read_1d::<VarLenUnicode>()?
                     .iter()
                     .map(|s| s.to_string())
                     .collect();
-                let codes = sub_group.dataset("codes")?.read_1d::<u32>()?;
-                categorical_data.push(Some((categories, codes.to_vec())));
+                let codes = sub_group.dataset("codes")?;
+                let ordered = sub_group.attr("ordered")?.read_scalar::<bool>()?;
+                categorical_data.push(Some((categories, codes, ordered)));
+                sub_groups.push(None);
             } else {
                 categorical_data.push(None);
+                sub_groups.push(sub_group);
             }
+
+            encoding_types.push(encoding_type);
         }
 
         Ok(Self {
@@ -66,9 +117,13 @@ impl DataReader {
             total_rows,
             encoding_types,
             categorical_data,
+            sub_groups,
+            raw,
         })
     }
 
+    /// Hyperslab-read rows `start..end` of every column, touching only the
+    /// slice of each underlying dataset that was asked for.
     fn read_chunk(&self, start: usize, chunk_size: usize) -> Result<Vec<Vec<String>>> {
         let end = (start + chunk_size).min(self.total_rows);
         let mut chunk_data = Vec::new();
@@ -84,22 +139,34 @@ impl DataReader {
                     .map(|s| s.to_string())
                     .collect(),
                 "categorical" => {
-                    if let Some((categories, codes)) = &self.categorical_data[i] {
-                        codes[start..end]
-                            .iter()
-                            .map(|&code| categories[code as usize].clone())
-                            .collect()
+                    if let Some((categories, codes, _)) = &self.categorical_data[i] {
+                        let codes = read_codes_slice(codes, start, end)?;
+                        if self.raw {
+                            codes.iter().map(|code| code.to_string()).collect()
+                        } else {
+                            codes
+                                .iter()
+                                .map(|&code| {
+                                    if code < 0 {
+                                        NA_TOKEN.to_string()
+                                    } else {
+                                        categories[code as usize].clone()
+                                    }
+                                })
+                                .collect()
+                        }
                     } else {
                         return Err(anyhow::anyhow!("Categorical data not found"));
                     }
                 }
-                "array" => self
-                    .group
-                    .dataset(name)?
-                    .read_slice_1d::<i64, _>(start..end)?
-                    .iter()
-                    .map(|&n| n.to_string())
-                    .collect(),
+                "array" => read_numeric_slice(&self.group.dataset(name)?, start, end)?,
+                "nullable-integer" | "nullable-boolean" => {
+                    if let Some(sub_group) = &self.sub_groups[i] {
+                        read_nullable_slice(sub_group, start, end)?
+                    } else {
+                        return Err(anyhow::anyhow!("Nullable group not found"));
+                    }
+                }
                 _ => {
                     return Err(anyhow::anyhow!(
                         "Unsupported encoding-type: {}",
@@ -117,19 +184,239 @@ impl DataReader {
         &self.headers
     }
 
+    /// One legend line per field: its `encoding-type`, plus the ordered
+    /// `categories` vocabulary and `ordered` flag for categorical fields.
+    /// Used by `--raw` to show how AnnData actually laid a column out on
+    /// disk alongside the un-decoded `codes`.
+    fn legend(&self) -> String {
+        self.headers
+            .iter()
+            .zip(&self.encoding_types)
+            .enumerate()
+            .map(
+                |(i, (name, encoding_type))| match &self.categorical_data[i] {
+                    Some((categories, _, ordered)) => format!(
+                        "# {}: {} (ordered={}, categories=[{}])",
+                        name,
+                        encoding_type,
+                        ordered,
+                        categories.join(", ")
+                    ),
+                    None => format!("# {}: {}", name, encoding_type),
+                },
+            )
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     fn total_rows(&self) -> usize {
         self.total_rows
     }
+
+    /// Stream up to `max_rows` rows starting at `start`, pulling `CHUNK_SIZE`
+    /// rows at a time so only one chunk is ever held in memory.
+    fn rows(&self, start: usize, max_rows: usize) -> RowIter<'_> {
+        RowIter::new(self, start, max_rows)
+    }
+}
+
+/// Decode a categorical `codes` dataset at its actual signed integer width.
+/// Pandas categoricals store `-1` for rows with a missing category, so codes
+/// are widened into `i64` rather than an unsigned type - reading them as
+/// unsigned would wrap `-1` into a huge positive index and silently alias it
+/// to `categories[0]`.
+pub(crate) fn read_codes_slice(
+    dataset: &hdf5::Dataset,
+    start: usize,
+    end: usize,
+) -> Result<Vec<i64>> {
+    let descriptor = dataset.dtype()?.to_descriptor()?;
+
+    Ok(match descriptor {
+        TypeDescriptor::Integer(IntSize::U1) => dataset
+            .read_slice_1d::<i8, _>(start..end)?
+            .iter()
+            .map(|&v| v as i64)
+            .collect(),
+        TypeDescriptor::Integer(IntSize::U2) => dataset
+            .read_slice_1d::<i16, _>(start..end)?
+            .iter()
+            .map(|&v| v as i64)
+            .collect(),
+        TypeDescriptor::Integer(IntSize::U4) => dataset
+            .read_slice_1d::<i32, _>(start..end)?
+            .iter()
+            .map(|&v| v as i64)
+            .collect(),
+        TypeDescriptor::Integer(IntSize::U8) => {
+            dataset.read_slice_1d::<i64, _>(start..end)?.to_vec()
+        }
+        other => {
+            return Err(anyhow::anyhow!(
+                "Unsupported codes datatype {:?} for dataset {:?}",
+                other,
+                dataset.name()
+            ))
+        }
+    })
+}
+
+/// Decode a numeric dataset slice according to its actual HDF5 datatype,
+/// dispatching to the matching Rust type instead of assuming `i64`.
+pub(crate) fn read_numeric_slice(
+    dataset: &hdf5::Dataset,
+    start: usize,
+    end: usize,
+) -> Result<Vec<String>> {
+    let descriptor = dataset.dtype()?.to_descriptor()?;
+
+    let values = match descriptor {
+        TypeDescriptor::Float(FloatSize::U4) => dataset
+            .read_slice_1d::<f32, _>(start..end)?
+            .iter()
+            .map(|v| format!("{:.*}", FLOAT_PRECISION, v))
+            .collect(),
+        TypeDescriptor::Float(FloatSize::U8) => dataset
+            .read_slice_1d::<f64, _>(start..end)?
+            .iter()
+            .map(|v| format!("{:.*}", FLOAT_PRECISION, v))
+            .collect(),
+        TypeDescriptor::Integer(IntSize::U1) => dataset
+            .read_slice_1d::<i8, _>(start..end)?
+            .iter()
+            .map(|v| v.to_string())
+            .collect(),
+        TypeDescriptor::Integer(IntSize::U2) => dataset
+            .read_slice_1d::<i16, _>(start..end)?
+            .iter()
+            .map(|v| v.to_string())
+            .collect(),
+        TypeDescriptor::Integer(IntSize::U4) => dataset
+            .read_slice_1d::<i32, _>(start..end)?
+            .iter()
+            .map(|v| v.to_string())
+            .collect(),
+        TypeDescriptor::Integer(IntSize::U8) => dataset
+            .read_slice_1d::<i64, _>(start..end)?
+            .iter()
+            .map(|v| v.to_string())
+            .collect(),
+        TypeDescriptor::Unsigned(IntSize::U1) => dataset
+            .read_slice_1d::<u8, _>(start..end)?
+            .iter()
+            .map(|v| v.to_string())
+            .collect(),
+        TypeDescriptor::Unsigned(IntSize::U2) => dataset
+            .read_slice_1d::<u16, _>(start..end)?
+            .iter()
+            .map(|v| v.to_string())
+            .collect(),
+        TypeDescriptor::Unsigned(IntSize::U4) => dataset
+            .read_slice_1d::<u32, _>(start..end)?
+            .iter()
+            .map(|v| v.to_string())
+            .collect(),
+        TypeDescriptor::Unsigned(IntSize::U8) => dataset
+            .read_slice_1d::<u64, _>(start..end)?
+            .iter()
+            .map(|v| v.to_string())
+            .collect(),
+        TypeDescriptor::Boolean | TypeDescriptor::Enum(_) => dataset
+            .read_slice_1d::<bool, _>(start..end)?
+            .iter()
+            .map(|&b| if b { "True" } else { "False" }.to_string())
+            .collect(),
+        other => {
+            return Err(anyhow::anyhow!(
+                "Unsupported datatype {:?} for dataset {:?}",
+                other,
+                dataset.name()
+            ))
+        }
+    };
+
+    Ok(values)
+}
+
+/// Decode a `nullable-integer`/`nullable-boolean` group's `values` slice,
+/// substituting `NA_TOKEN` wherever the parallel `mask` slice is set.
+fn read_nullable_slice(sub_group: &hdf5::Group, start: usize, end: usize) -> Result<Vec<String>> {
+    let values = read_numeric_slice(&sub_group.dataset("values")?, start, end)?;
+    let mask = sub_group
+        .dataset("mask")?
+        .read_slice_1d::<bool, _>(start..end)?;
+
+    Ok(values
+        .into_iter()
+        .zip(mask.iter())
+        .map(|(v, &masked)| if masked { NA_TOKEN.to_string() } else { v })
+        .collect())
+}
+
+/// Pull iterator over the rows of a `DataReader`, honoring a `start..start+max_rows`
+/// bound without ever materializing more than one `CHUNK_SIZE` chunk at a time.
+struct RowIter<'a> {
+    reader: &'a DataReader,
+    end: usize,
+    pos: usize,
+    chunk: Vec<Vec<String>>,
+    chunk_start: usize,
+}
+
+impl<'a> RowIter<'a> {
+    fn new(reader: &'a DataReader, start: usize, max_rows: usize) -> Self {
+        let end = start.saturating_add(max_rows).min(reader.total_rows);
+        Self {
+            reader,
+            end,
+            pos: start,
+            chunk: Vec::new(),
+            chunk_start: start,
+        }
+    }
 }
 
-// Show the first n rows of a group
-pub fn show_head(file: &File, group_name: &str, lines: usize) -> Result<()> {
-    let reader = DataReader::new(file, group_name)?;
+impl<'a> Iterator for RowIter<'a> {
+    type Item = Result<Vec<String>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.end {
+            return None;
+        }
+
+        let in_chunk = !self.chunk.is_empty() && self.pos - self.chunk_start < self.chunk[0].len();
+        if !in_chunk {
+            self.chunk_start = self.pos;
+            let chunk_len = CHUNK_SIZE.min(self.end - self.pos);
+            match self.reader.read_chunk(self.pos, chunk_len) {
+                Ok(chunk) => self.chunk = chunk,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+
+        let local = self.pos - self.chunk_start;
+        let row: Vec<String> = self.chunk.iter().map(|col| col[local].clone()).collect();
+        self.pos += 1;
+        Some(Ok(row))
+    }
+}
+
+// Show `max_rows` rows starting at `start`, restricted to `columns` if given
+pub fn show_head(
+    file: &File,
+    group_name: &str,
+    start: usize,
+    max_rows: usize,
+    columns: Option<&[String]>,
+    raw: bool,
+) -> Result<()> {
+    let reader = DataReader::new(file, group_name, columns, raw)?;
+    if raw {
+        println!("{}", reader.legend());
+    }
     println!("{}", reader.get_headers().join("\t"));
-    let chunk_data = reader.read_chunk(0, lines)?;
-    for row_idx in 0..lines.min(reader.total_rows()) {
-        let row: Vec<String> = chunk_data.iter().map(|col| col[row_idx].clone()).collect();
-        println!("{}", row.join("\t"));
+    for row in reader.rows(start, max_rows) {
+        println!("{}", row?.join("\t"));
     }
     Ok(())
 }
@@ -145,20 +432,23 @@ fn pipe_write(content: &str) -> Result<()> {
     Ok(())
 }
 
-// Show all rows of a group
-pub fn show_less(file: &File, group_name: &str) -> Result<()> {
-    let reader = DataReader::new(file, group_name)?;
+// Show up to `max_rows` rows starting at `start`, restricted to `columns` if given
+pub fn show_less(
+    file: &File,
+    group_name: &str,
+    start: usize,
+    max_rows: usize,
+    columns: Option<&[String]>,
+    raw: bool,
+) -> Result<()> {
+    let reader = DataReader::new(file, group_name, columns, raw)?;
+    if raw {
+        pipe_write(&reader.legend())?;
+    }
     pipe_write(&reader.get_headers().join("\t"))?;
 
-    const CHUNK_SIZE: usize = 1000;
-    let mut start = 0;
-    while start < reader.total_rows() {
-        let chunk_data = reader.read_chunk(start, CHUNK_SIZE)?;
-        for row_idx in 0..chunk_data[0].len() {
-            let row: Vec<String> = chunk_data.iter().map(|col| col[row_idx].clone()).collect();
-            pipe_write(&row.join("\t"))?;
-        }
-        start += CHUNK_SIZE;
+    for row in reader.rows(start, max_rows) {
+        pipe_write(&row?.join("\t"))?;
     }
     Ok(())
 }
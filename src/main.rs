@@ -1,4 +1,7 @@
-use adview::{show_fields, show_head, show_less, show_shapes};
+use adview::{
+    export, show_fields, show_head, show_less, show_matrix_head, show_shapes, show_stats,
+    ExportFormat, SparseRender,
+};
 use anyhow::{Context, Result};
 use clap::{Args, Parser, Subcommand};
 use hdf5::File;
@@ -24,16 +27,99 @@ struct FileArg {
     file: PathBuf,
 }
 
+#[derive(Args)]
+struct SelectArg {
+    /// Select a subset of fields by name, preserving order
+    #[arg(long, value_delimiter = ',')]
+    columns: Option<Vec<String>>,
+
+    /// Row range to display, formatted <start>:<end> (either side may be omitted)
+    #[arg(long)]
+    range: Option<String>,
+
+    /// Show the on-disk encoding of each field instead of its decoded form:
+    /// categorical columns print their raw integer codes, preceded by a
+    /// legend with one line per field giving its encoding-type (and, for
+    /// categorical fields, the ordered flag and categories)
+    #[arg(long)]
+    raw: bool,
+}
+
 #[derive(Args)]
 struct HeadArg {
     #[clap(flatten)]
     file_arg: FileArg,
 
+    #[clap(flatten)]
+    select: SelectArg,
+
     /// Number of lines to show
     #[arg(short = 'n', long = "lines", default_value = "10")]
     lines: usize,
 }
 
+#[derive(Args)]
+struct AllArg {
+    #[clap(flatten)]
+    file_arg: FileArg,
+
+    #[clap(flatten)]
+    select: SelectArg,
+}
+
+#[derive(Args)]
+struct MatrixHeadArg {
+    #[clap(flatten)]
+    file_arg: FileArg,
+
+    /// Number of rows to show
+    #[arg(short = 'n', long = "lines", default_value = "10")]
+    lines: usize,
+
+    /// Render sparse rows densely (zero-filled) instead of as col:value lists
+    #[arg(long)]
+    dense: bool,
+}
+
+#[derive(Args)]
+struct LayerHeadArg {
+    #[clap(flatten)]
+    matrix_arg: MatrixHeadArg,
+
+    /// Layer name under /layers
+    layer: String,
+}
+
+#[derive(Args)]
+struct ExportArg {
+    #[clap(flatten)]
+    file_arg: FileArg,
+
+    /// obs or var
+    group: String,
+
+    /// Output format
+    #[arg(long, value_parser = ["csv", "tsv", "json", "arrow", "parquet"], default_value = "tsv")]
+    format: String,
+
+    /// Output file path
+    #[arg(long, short = 'o')]
+    output: PathBuf,
+}
+
+#[derive(Args)]
+struct StatsArg {
+    #[clap(flatten)]
+    file_arg: FileArg,
+
+    /// obs or var
+    group: String,
+
+    /// Number of most frequent categories to report for categorical/string fields
+    #[arg(long = "top-k", default_value = "5")]
+    top_k: usize,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Show first n obs
@@ -41,19 +127,64 @@ enum Commands {
     ObsHead(#[clap(flatten)] HeadArg),
     /// Show all obs
     #[command(visible_alias = "oa")]
-    ObsAll(#[clap(flatten)] FileArg),
+    ObsAll(#[clap(flatten)] AllArg),
     /// Show first n var
     #[command(visible_alias = "vh")]
     VarHead(#[clap(flatten)] HeadArg),
     /// Show all var
     #[command(visible_alias = "va")]
-    VarAll(#[clap(flatten)] FileArg),
+    VarAll(#[clap(flatten)] AllArg),
     /// Show shapes of obs and var
     #[command(visible_alias = "s")]
     Shape(#[clap(flatten)] FileArg),
     /// Show fields in obs and var
     #[command(visible_alias = "f")]
     Field(#[clap(flatten)] FileArg),
+    /// Show first n rows of the X matrix
+    #[command(visible_alias = "xh")]
+    XHead(#[clap(flatten)] MatrixHeadArg),
+    /// Show first n rows of a named layer
+    #[command(visible_alias = "lh")]
+    Layer(#[clap(flatten)] LayerHeadArg),
+    /// Export obs/var to a typed columnar file
+    #[command(visible_alias = "e")]
+    Export(#[clap(flatten)] ExportArg),
+    /// Show per-field summary statistics
+    #[command(visible_alias = "st")]
+    Stats(#[clap(flatten)] StatsArg),
+}
+
+fn sparse_render(dense: bool) -> SparseRender {
+    if dense {
+        SparseRender::Dense
+    } else {
+        SparseRender::Sparse
+    }
+}
+
+/// Resolve `--range <start>:<end>` (either side optional) against a default
+/// row count, yielding the `(start, max_rows)` pair `DataReader` expects.
+fn resolve_range(range: &Option<String>, default_count: usize) -> Result<(usize, usize)> {
+    let Some(range) = range else {
+        return Ok((0, default_count));
+    };
+
+    let (start_part, end_part) = range
+        .split_once(':')
+        .context("--range must be formatted as <start>:<end>")?;
+    let start: usize = if start_part.is_empty() {
+        0
+    } else {
+        start_part.parse().context("invalid range start")?
+    };
+    let max_rows = if end_part.is_empty() {
+        default_count
+    } else {
+        let end: usize = end_part.parse().context("invalid range end")?;
+        end.saturating_sub(start)
+    };
+
+    Ok((start, max_rows))
 }
 
 fn main() -> Result<()> {
@@ -65,12 +196,73 @@ fn main() -> Result<()> {
     };
 
     match cli.command {
-        Commands::ObsHead(args) => show_head(&open_file(&args.file_arg.file)?, "obs", args.lines)?,
-        Commands::ObsAll(args) => show_less(&open_file(&args.file)?, "obs")?,
-        Commands::VarHead(args) => show_head(&open_file(&args.file_arg.file)?, "var", args.lines)?,
-        Commands::VarAll(args) => show_less(&open_file(&args.file)?, "var")?,
+        Commands::ObsHead(args) => {
+            let (start, max_rows) = resolve_range(&args.select.range, args.lines)?;
+            show_head(
+                &open_file(&args.file_arg.file)?,
+                "obs",
+                start,
+                max_rows,
+                args.select.columns.as_deref(),
+                args.select.raw,
+            )?
+        }
+        Commands::ObsAll(args) => {
+            let (start, max_rows) = resolve_range(&args.select.range, usize::MAX)?;
+            show_less(
+                &open_file(&args.file_arg.file)?,
+                "obs",
+                start,
+                max_rows,
+                args.select.columns.as_deref(),
+                args.select.raw,
+            )?
+        }
+        Commands::VarHead(args) => {
+            let (start, max_rows) = resolve_range(&args.select.range, args.lines)?;
+            show_head(
+                &open_file(&args.file_arg.file)?,
+                "var",
+                start,
+                max_rows,
+                args.select.columns.as_deref(),
+                args.select.raw,
+            )?
+        }
+        Commands::VarAll(args) => {
+            let (start, max_rows) = resolve_range(&args.select.range, usize::MAX)?;
+            show_less(
+                &open_file(&args.file_arg.file)?,
+                "var",
+                start,
+                max_rows,
+                args.select.columns.as_deref(),
+                args.select.raw,
+            )?
+        }
         Commands::Shape(args) => show_shapes(&open_file(&args.file)?)?,
         Commands::Field(args) => show_fields(&open_file(&args.file)?)?,
+        Commands::XHead(args) => show_matrix_head(
+            &open_file(&args.file_arg.file)?,
+            "X",
+            args.lines,
+            sparse_render(args.dense),
+        )?,
+        Commands::Layer(args) => show_matrix_head(
+            &open_file(&args.matrix_arg.file_arg.file)?,
+            &format!("layers/{}", args.layer),
+            args.matrix_arg.lines,
+            sparse_render(args.matrix_arg.dense),
+        )?,
+        Commands::Export(args) => export(
+            &open_file(&args.file_arg.file)?,
+            &args.group,
+            ExportFormat::parse(&args.format)?,
+            &args.output,
+        )?,
+        Commands::Stats(args) => {
+            show_stats(&open_file(&args.file_arg.file)?, &args.group, args.top_k)?
+        }
     }
 
     Ok(())
@@ -0,0 +1,370 @@
+use crate::{read_codes_slice, CHUNK_SIZE, FLOAT_PRECISION, NA_TOKEN};
+use anyhow::Result;
+use hdf5::types::{FloatSize, IntSize, TypeDescriptor, VarLenUnicode};
+use hdf5::{Dataset, File, Group};
+use std::collections::HashMap;
+
+enum FieldKind {
+    Numeric(Dataset),
+    NullableNumeric {
+        values: Dataset,
+        mask: Dataset,
+    },
+    Categorical {
+        codes: Dataset,
+        categories: Vec<String>,
+    },
+    StringArray(Dataset),
+}
+
+struct Field {
+    kind: FieldKind,
+    total_rows: usize,
+}
+
+/// Running mean/variance via Welford's online algorithm, plus min/max.
+struct NumericAcc {
+    n: usize,
+    mean: f64,
+    m2: f64,
+    min: f64,
+    max: f64,
+}
+
+impl NumericAcc {
+    fn new() -> Self {
+        Self {
+            n: 0,
+            mean: 0.0,
+            m2: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn update(&mut self, x: f64) {
+        self.n += 1;
+        let d = x - self.mean;
+        self.mean += d / self.n as f64;
+        self.m2 += d * (x - self.mean);
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+    }
+
+    fn variance(&self) -> Option<f64> {
+        (self.n > 1).then(|| self.m2 / (self.n - 1) as f64)
+    }
+}
+
+enum FieldSummary {
+    Numeric {
+        n_missing: usize,
+        acc: NumericAcc,
+    },
+    Categorical {
+        n_missing: usize,
+        counts: HashMap<String, usize>,
+    },
+}
+
+/// Compute and print a single-pass per-field summary for every column of
+/// `obs`/`var`: count/n-missing plus min/max/mean/variance for numeric
+/// fields, cardinality plus top-k value counts for categorical/string ones.
+pub fn show_stats(file: &File, group_name: &str, top_k: usize) -> Result<()> {
+    let group = file.group(group_name)?;
+    let headers = group.member_names()?;
+
+    for name in &headers {
+        let field = open_field(&group, name)?;
+        let summary = summarize_field(&field)?;
+        println!(
+            "{}",
+            format_summary(name, field.total_rows, &summary, top_k)
+        );
+    }
+    Ok(())
+}
+
+fn open_field(group: &Group, name: &str) -> Result<Field> {
+    match group.dataset(name) {
+        Ok(dataset) => {
+            let encoding_type = dataset
+                .attr("encoding-type")?
+                .read_scalar::<VarLenUnicode>()?
+                .to_string();
+            let total_rows = dataset.shape()[0];
+            let kind = match encoding_type.as_str() {
+                "string-array" => FieldKind::StringArray(dataset),
+                "array" => FieldKind::Numeric(dataset),
+                other => {
+                    return Err(anyhow::anyhow!(
+                        "Unsupported encoding-type for {}: {}",
+                        name,
+                        other
+                    ))
+                }
+            };
+            Ok(Field { kind, total_rows })
+        }
+        Err(_) => {
+            let sub_group = group.group(name)?;
+            let encoding_type = sub_group
+                .attr("encoding-type")?
+                .read_scalar::<VarLenUnicode>()?
+                .to_string();
+            match encoding_type.as_str() {
+                "categorical" => {
+                    let categories: Vec<String> = sub_group
+                        .dataset("categories")?
+                        .read_1d::<VarLenUnicode>()?
+                        .iter()
+                        .map(|s| s.to_string())
+                        .collect();
+                    let codes = sub_group.dataset("codes")?;
+                    let total_rows = codes.shape()[0];
+                    Ok(Field {
+                        kind: FieldKind::Categorical { codes, categories },
+                        total_rows,
+                    })
+                }
+                "nullable-integer" | "nullable-boolean" => {
+                    let values = sub_group.dataset("values")?;
+                    let mask = sub_group.dataset("mask")?;
+                    let total_rows = values.shape()[0];
+                    Ok(Field {
+                        kind: FieldKind::NullableNumeric { values, mask },
+                        total_rows,
+                    })
+                }
+                other => Err(anyhow::anyhow!(
+                    "Unsupported encoding-type for {}: {}",
+                    name,
+                    other
+                )),
+            }
+        }
+    }
+}
+
+fn summarize_field(field: &Field) -> Result<FieldSummary> {
+    match &field.kind {
+        FieldKind::Numeric(dataset) => {
+            let mut acc = NumericAcc::new();
+            let mut n_missing = 0;
+            for_each_chunk(field.total_rows, |start, end| {
+                for x in read_f64_slice(dataset, start, end)? {
+                    if x.is_nan() {
+                        n_missing += 1;
+                    } else {
+                        acc.update(x);
+                    }
+                }
+                Ok(())
+            })?;
+            Ok(FieldSummary::Numeric { n_missing, acc })
+        }
+        FieldKind::NullableNumeric { values, mask } => {
+            let mut acc = NumericAcc::new();
+            let mut n_missing = 0;
+            for_each_chunk(field.total_rows, |start, end| {
+                let xs = read_f64_slice(values, start, end)?;
+                let masked = mask.read_slice_1d::<bool, _>(start..end)?;
+                for (x, &is_missing) in xs.iter().zip(masked.iter()) {
+                    if is_missing {
+                        n_missing += 1;
+                    } else {
+                        acc.update(*x);
+                    }
+                }
+                Ok(())
+            })?;
+            Ok(FieldSummary::Numeric { n_missing, acc })
+        }
+        FieldKind::Categorical { codes, categories } => {
+            let mut counts = HashMap::new();
+            let mut n_missing = 0;
+            for_each_chunk(field.total_rows, |start, end| {
+                for code in read_codes_slice(codes, start, end)? {
+                    if code < 0 {
+                        n_missing += 1;
+                    } else {
+                        *counts.entry(categories[code as usize].clone()).or_insert(0) += 1;
+                    }
+                }
+                Ok(())
+            })?;
+            Ok(FieldSummary::Categorical { n_missing, counts })
+        }
+        FieldKind::StringArray(dataset) => {
+            let mut counts = HashMap::new();
+            let mut n_missing = 0;
+            for_each_chunk(field.total_rows, |start, end| {
+                for value in dataset
+                    .read_slice_1d::<VarLenUnicode, _>(start..end)?
+                    .iter()
+                {
+                    let value = value.to_string();
+                    if value.is_empty() || value == NA_TOKEN {
+                        n_missing += 1;
+                    } else {
+                        *counts.entry(value).or_insert(0) += 1;
+                    }
+                }
+                Ok(())
+            })?;
+            Ok(FieldSummary::Categorical { n_missing, counts })
+        }
+    }
+}
+
+fn for_each_chunk(
+    total_rows: usize,
+    mut visit: impl FnMut(usize, usize) -> Result<()>,
+) -> Result<()> {
+    let mut start = 0;
+    while start < total_rows {
+        let end = (start + CHUNK_SIZE).min(total_rows);
+        visit(start, end)?;
+        start = end;
+    }
+    Ok(())
+}
+
+/// Decode a numeric dataset slice as `f64`, dispatching on its actual dtype.
+fn read_f64_slice(dataset: &Dataset, start: usize, end: usize) -> Result<Vec<f64>> {
+    let descriptor = dataset.dtype()?.to_descriptor()?;
+    Ok(match descriptor {
+        TypeDescriptor::Float(FloatSize::U4) => dataset
+            .read_slice_1d::<f32, _>(start..end)?
+            .iter()
+            .map(|&v| v as f64)
+            .collect(),
+        TypeDescriptor::Float(FloatSize::U8) => {
+            dataset.read_slice_1d::<f64, _>(start..end)?.to_vec()
+        }
+        TypeDescriptor::Integer(IntSize::U1) => dataset
+            .read_slice_1d::<i8, _>(start..end)?
+            .iter()
+            .map(|&v| v as f64)
+            .collect(),
+        TypeDescriptor::Integer(IntSize::U2) => dataset
+            .read_slice_1d::<i16, _>(start..end)?
+            .iter()
+            .map(|&v| v as f64)
+            .collect(),
+        TypeDescriptor::Integer(IntSize::U4) => dataset
+            .read_slice_1d::<i32, _>(start..end)?
+            .iter()
+            .map(|&v| v as f64)
+            .collect(),
+        TypeDescriptor::Integer(IntSize::U8) => dataset
+            .read_slice_1d::<i64, _>(start..end)?
+            .iter()
+            .map(|&v| v as f64)
+            .collect(),
+        TypeDescriptor::Unsigned(IntSize::U1) => dataset
+            .read_slice_1d::<u8, _>(start..end)?
+            .iter()
+            .map(|&v| v as f64)
+            .collect(),
+        TypeDescriptor::Unsigned(IntSize::U2) => dataset
+            .read_slice_1d::<u16, _>(start..end)?
+            .iter()
+            .map(|&v| v as f64)
+            .collect(),
+        TypeDescriptor::Unsigned(IntSize::U4) => dataset
+            .read_slice_1d::<u32, _>(start..end)?
+            .iter()
+            .map(|&v| v as f64)
+            .collect(),
+        TypeDescriptor::Unsigned(IntSize::U8) => dataset
+            .read_slice_1d::<u64, _>(start..end)?
+            .iter()
+            .map(|&v| v as f64)
+            .collect(),
+        TypeDescriptor::Boolean | TypeDescriptor::Enum(_) => dataset
+            .read_slice_1d::<bool, _>(start..end)?
+            .iter()
+            .map(|&b| if b { 1.0 } else { 0.0 })
+            .collect(),
+        other => {
+            return Err(anyhow::anyhow!(
+                "Unsupported datatype {:?} for dataset {:?}",
+                other,
+                dataset.name()
+            ))
+        }
+    })
+}
+
+fn format_summary(name: &str, total_rows: usize, summary: &FieldSummary, top_k: usize) -> String {
+    match summary {
+        FieldSummary::Numeric { n_missing, acc } => {
+            let variance = acc
+                .variance()
+                .map(|v| format!("{:.*}", FLOAT_PRECISION, v))
+                .unwrap_or_else(|| "NA".to_string());
+            format!(
+                "{}\tnumeric\tcount={}\tmissing={}\tmin={:.*}\tmax={:.*}\tmean={:.*}\tvar={}",
+                name,
+                total_rows,
+                n_missing,
+                FLOAT_PRECISION,
+                acc.min,
+                FLOAT_PRECISION,
+                acc.max,
+                FLOAT_PRECISION,
+                acc.mean,
+                variance
+            )
+        }
+        FieldSummary::Categorical { n_missing, counts } => {
+            let mut ranked: Vec<(&String, &usize)> = counts.iter().collect();
+            ranked.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            let top = ranked
+                .into_iter()
+                .take(top_k)
+                .map(|(value, count)| format!("{}({})", value, count))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(
+                "{}\tcategorical\tcount={}\tmissing={}\tcardinality={}\ttop={}",
+                name,
+                total_rows,
+                n_missing,
+                counts.len(),
+                top
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_acc_tracks_min_max_mean_and_variance() {
+        let mut acc = NumericAcc::new();
+        for x in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            acc.update(x);
+        }
+
+        assert_eq!(acc.min, 2.0);
+        assert_eq!(acc.max, 9.0);
+        assert_eq!(acc.mean, 5.0);
+        assert_eq!(acc.variance(), Some(4.571428571428571));
+    }
+
+    #[test]
+    fn numeric_acc_variance_is_none_until_two_samples() {
+        let mut acc = NumericAcc::new();
+        assert_eq!(acc.variance(), None);
+
+        acc.update(1.0);
+        assert_eq!(acc.variance(), None);
+
+        acc.update(2.0);
+        assert_eq!(acc.variance(), Some(0.5));
+    }
+}